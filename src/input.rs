@@ -1,37 +1,95 @@
 use std::iter::Peekable;
-use std::str::Chars;
+use std::str::CharIndices;
+
+/// A byte range and line/column position describing where a `Token` was found in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
 
 pub struct BufferedInput<'a> {
-    buffer: String,
-    chars: Peekable<Chars<'a>>,
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    start: usize,
+    pos: usize,
+    line: usize,
+    col: usize,
+    token_start: Span,
+}
+
+/// A previously recorded cursor position, produced by `BufferedInput::checkpoint` and
+/// consumed by `BufferedInput::restore` to rewind speculative lexing
+#[derive(Clone)]
+pub struct Checkpoint<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    start: usize,
+    pos: usize,
+    line: usize,
+    col: usize,
+    token_start: Span,
 }
 
 impl<'a> BufferedInput<'a> {
     /// Create a new buffered lexer input
     pub(crate) fn new(input: &'a str) -> BufferedInput<'a> {
         BufferedInput {
-            buffer: String::new(),
-            chars: input.chars().peekable(),
+            input,
+            chars: input.char_indices().peekable(),
+            start: 0,
+            pos: 0,
+            line: 1,
+            col: 1,
+            token_start: Span { start: 0, end: 0, line: 1, col: 1 },
         }
     }
 
-    /// Copy out the buffer and clear it
-    pub fn take_buffer(&mut self) -> String {
-        let buffer = self.buffer.clone();
-        self.buffer.clear();
-        buffer
+    /// Advance the running position/line/col counters past `c`
+    fn advance(&mut self, c: char) {
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    /// Mark the current position as the start of the next token
+    pub(crate) fn mark_token_start(&mut self) {
+        self.start = self.pos;
+        self.token_start = Span { start: self.pos, end: self.pos, line: self.line, col: self.col };
+    }
+
+    /// Build the `Span` covering everything accepted since `mark_token_start`
+    pub(crate) fn take_span(&self) -> Span {
+        Span { end: self.pos, ..self.token_start }
+    }
+
+    /// Borrow the slice of the original input accepted since `mark_token_start`, and reset
+    /// so the next token starts fresh
+    pub fn take_buffer(&mut self) -> &'a str {
+        let text = &self.input[self.start..self.pos];
+        self.start = self.pos;
+        text
     }
 
     /// Peek at the next character
     #[inline(always)]
     pub fn peek(&mut self) -> Option<char> {
-        self.chars.peek().map(|&c| c)
+        self.chars.peek().map(|&(_, c)| c)
     }
 
     /// Get the next character but don't push it to the buffer
     #[inline(always)]
     pub fn skip(&mut self) -> Option<char> {
-        self.chars.next()
+        let c = self.chars.next().map(|(_, c)| c);
+        if let Some(c) = c {
+            self.advance(c);
+        }
+        c
     }
 
     /// Skip if the given predicate is true
@@ -50,10 +108,10 @@ impl<'a> BufferedInput<'a> {
         while let Some(_) = self.skip_if(&predicate) {}
     }
 
-    /// Retrieve the next character and increment the input position
+    /// Retrieve the next character and include it in the slice returned by `take_buffer`
     pub fn accept(&mut self) -> Option<char> {
-        if let Some(c) = self.chars.next() {
-            self.buffer.push(c);
+        if let Some((_, c)) = self.chars.next() {
+            self.advance(c);
             Some(c)
         } else {
             None
@@ -80,10 +138,18 @@ impl<'a> BufferedInput<'a> {
     ///
     /// This is useful for matching multi-character tokens:
     /// ```rust
-    /// match c {
-    ///     '=' => input.accept_or(|&c| c == '=', TokenKind::DoubleEquals, TokenKind::Equals),
-    ///     _ => {},
+    /// use generic_lexer::BufferedInput;
+    ///
+    /// #[derive(Debug)]
+    /// enum TokenKind { DoubleEquals, Equals, Other }
+    ///
+    /// fn match_equals(c: char, input: &mut BufferedInput) -> TokenKind {
+    ///     match c {
+    ///         '=' => input.accept_or(|&c| c == '=', TokenKind::DoubleEquals, TokenKind::Equals),
+    ///         _ => TokenKind::Other,
+    ///     }
     /// }
+    /// # let _ = match_equals;
     /// ```
     pub fn accept_or<P: Fn(&char) -> bool, T>(&mut self, predicate: P, ok: T, default: T) -> T {
         if let Some(_) = self.accept_if(predicate) {
@@ -98,4 +164,97 @@ impl<'a> BufferedInput<'a> {
     pub fn skip_whitespace(&mut self) {
         self.skip_while(char::is_ascii_whitespace);
     }
-}
\ No newline at end of file
+
+    /// Borrow everything from the start of the current token onward, i.e. the characters
+    /// already accepted into the buffer plus the as-yet-unconsumed remainder of the input.
+    /// Used by matchers (such as `RuleSet`) that need to re-test already-accepted characters
+    /// against a pattern.
+    pub fn remainder(&self) -> &'a str {
+        &self.input[self.start..]
+    }
+
+    /// Advance the cursor by `n` bytes, extending the current token's buffer to cover them
+    ///
+    /// `n` must land on a char boundary within the current `remainder()`.
+    pub fn advance_by(&mut self, n: usize) {
+        let target = self.pos + n;
+        while self.pos < target {
+            let c = self.chars.next().expect("advance_by: n exceeds remaining input").1;
+            self.advance(c);
+        }
+    }
+
+    /// Record the current cursor position so a speculative lex can later be rolled back
+    /// with `restore`
+    pub fn checkpoint(&self) -> Checkpoint<'a> {
+        Checkpoint {
+            chars: self.chars.clone(),
+            start: self.start,
+            pos: self.pos,
+            line: self.line,
+            col: self.col,
+            token_start: self.token_start,
+        }
+    }
+
+    /// Rewind the cursor to a previously recorded `checkpoint`, discarding anything lexed
+    /// since then
+    pub fn restore(&mut self, checkpoint: Checkpoint<'a>) {
+        self.chars = checkpoint.chars;
+        self.start = checkpoint.start;
+        self.pos = checkpoint.pos;
+        self.line = checkpoint.line;
+        self.col = checkpoint.col;
+        self.token_start = checkpoint.token_start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_byte_span_and_column_tracking() {
+        // 'é' is 2 bytes in UTF-8, so byte offsets must advance by 2 while the column,
+        // which counts chars rather than bytes, only advances by 1
+        let mut input = BufferedInput::new("é\nab");
+
+        input.mark_token_start();
+        input.accept();
+        assert_eq!(input.take_span(), Span { start: 0, end: 2, line: 1, col: 1 });
+
+        input.mark_token_start();
+        input.accept(); // '\n'
+        assert_eq!(input.take_span(), Span { start: 2, end: 3, line: 1, col: 2 });
+
+        input.mark_token_start();
+        input.accept(); // 'a', now on line 2
+        assert_eq!(input.take_span(), Span { start: 3, end: 4, line: 2, col: 1 });
+    }
+
+    #[test]
+    fn checkpoint_restores_mid_token_state_exactly() {
+        let mut input = BufferedInput::new("abcdef");
+
+        input.mark_token_start();
+        input.accept(); // 'a'
+        input.accept(); // 'b'
+
+        let checkpoint = input.checkpoint();
+
+        input.accept(); // 'c'
+        input.accept(); // 'd'
+        assert_eq!(input.take_buffer(), "abcd");
+
+        input.restore(checkpoint);
+
+        // re-lexing from the checkpoint must reproduce exactly what was consumed before
+        input.accept(); // 'c' again
+        input.accept(); // 'd' again
+        assert_eq!(input.take_buffer(), "abcd");
+
+        input.mark_token_start();
+        input.accept(); // 'e'
+        assert_eq!(input.take_span(), Span { start: 4, end: 5, line: 1, col: 5 });
+    }
+}
@@ -1,22 +1,29 @@
 mod input;
+mod peekable;
+mod ruleset;
 
-pub use input::BufferedInput;
+pub use input::{BufferedInput, Checkpoint, Span};
+pub use peekable::PeekableLexer;
+pub use ruleset::RuleSet;
 
 use std::error::Error;
 use std::fmt;
 
-/// A token with a kind (usually an enum representing distinct token types) and its source text
+/// A token with a kind (usually an enum representing distinct token types) and a `&str` slice
+/// of its source text, borrowed straight from the input the `Lexer` was created with
 
-#[derive(Debug)]
-pub struct Token<K> {
+#[derive(Debug, Clone)]
+pub struct Token<'a, K> {
     kind: K,
-    text: String,
+    text: &'a str,
+    span: Span,
+    trivia: bool,
 }
 
-impl<K> Token<K> {
-    /// Create a new token with the given kind and text
-    pub fn new(kind: K, text: String) -> Token<K> {
-        Token { kind, text }
+impl<'a, K> Token<'a, K> {
+    /// Create a new token with the given kind, text and span
+    pub fn new(kind: K, text: &'a str, span: Span) -> Token<'a, K> {
+        Token { kind, text, span, trivia: false }
     }
 
     /// Return the token's kind (usually an enum)
@@ -25,16 +32,29 @@ impl<K> Token<K> {
     }
 
     /// Return the token's text
-    pub fn text(&self) -> &String {
-        &self.text
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Return the token's span (byte offsets plus starting line/column) in the source
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn into_text(self) -> String {
+    /// Whether this token is whitespace/comment trivia rather than meaningful source text
+    ///
+    /// Only ever `true` for lexers built with `Lexer::with_trivia`; see
+    /// `Lexer::iter_including_trivia`.
+    pub fn is_trivia(&self) -> bool {
+        self.trivia
+    }
+
+    pub fn into_text(self) -> &'a str {
         self.text
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum MatchError {
     Unexpected(char),
     Custom(String),
@@ -73,6 +93,7 @@ pub struct Lexer<'a, K> {
     input: BufferedInput<'a>,
     matcher: &'a dyn Matcher<K>,
     skip_whitespace: bool,
+    trivia: Option<&'a dyn Fn(&K) -> bool>,
 }
 
 impl<'a, K> Lexer<'a, K> {
@@ -81,19 +102,51 @@ impl<'a, K> Lexer<'a, K> {
             input: BufferedInput::new(input),
             matcher,
             skip_whitespace,
+            trivia: None,
         }
     }
-}
 
-impl<'a, K> Iterator  for Lexer<'a, K> {
-    type Item = MatchResult<Token<K>>;
+    /// Build a lossless lexer: whitespace is never silently discarded, so `matcher` must
+    /// return a real kind for it (e.g. via `RuleSet` with `skip: false`). Every token is
+    /// passed through `is_trivia`, which marks the whitespace/comment ones so that the
+    /// default, filtered `next()` can be told apart from `iter_including_trivia`, where
+    /// concatenating every token's `text()` reproduces the input byte-for-byte.
+    pub fn with_trivia(input: &'a str, matcher: &'a dyn Matcher<K>, is_trivia: &'a dyn Fn(&K) -> bool) -> Lexer<'a, K> {
+        Lexer {
+            input: BufferedInput::new(input),
+            matcher,
+            skip_whitespace: false,
+            trivia: Some(is_trivia),
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Record the current lexing position so a speculative parse can roll back with `restore`
+    pub fn checkpoint(&self) -> Checkpoint<'a> {
+        self.input.checkpoint()
+    }
+
+    /// Rewind to a previously recorded `checkpoint`, discarding anything lexed since then
+    pub fn restore(&mut self, checkpoint: Checkpoint<'a>) {
+        self.input.restore(checkpoint);
+    }
+
+    /// Iterate over every token the matcher produces, including ones marked as trivia by
+    /// `with_trivia`, unlike the filtered `Iterator` impl
+    pub fn iter_including_trivia(&mut self) -> IncludingTrivia<'a, '_, K> {
+        IncludingTrivia { lexer: self }
+    }
+
+    /// Lex a single token, applying `skip_whitespace` and tagging trivia, but without
+    /// filtering trivia out of the result
+    fn lex_one(&mut self) -> Option<MatchResult<Token<'a, K>>> {
         // skip whitespace
         if self.skip_whitespace {
             self.input.skip_whitespace();
         }
 
+        // the token starts here, after any whitespace has been skipped
+        self.input.mark_token_start();
+
         // get first character
         let first_char = match self.input.accept() {
             Some(byte) => byte,
@@ -107,7 +160,102 @@ impl<'a, K> Iterator  for Lexer<'a, K> {
         };
 
         // create a `Token` wrapper and return it
-        Some(Ok(Token::new(kind, self.input.take_buffer())))
+        let span = self.input.take_span();
+        let mut token = Token::new(kind, self.input.take_buffer(), span);
+        if let Some(is_trivia) = self.trivia {
+            token.trivia = is_trivia(token.kind());
+        }
+        Some(Ok(token))
     }
 }
 
+impl<'a, K> Iterator  for Lexer<'a, K> {
+    type Item = MatchResult<Token<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.lex_one()? {
+                Ok(token) if token.is_trivia() => continue,
+                item => return Some(item),
+            }
+        }
+    }
+}
+
+/// Iterator adaptor returned by `Lexer::iter_including_trivia` that yields every token,
+/// including ones marked as trivia
+pub struct IncludingTrivia<'a, 'b, K> {
+    lexer: &'b mut Lexer<'a, K>,
+}
+
+impl<'a, 'b, K> Iterator for IncludingTrivia<'a, 'b, K> {
+    type Item = MatchResult<Token<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lexer.lex_one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Kind {
+        Word,
+        Space,
+    }
+
+    fn matcher(first_char: char, input: &mut BufferedInput) -> MatchResult<Kind> {
+        if first_char.is_whitespace() {
+            input.accept_while(|c| c.is_whitespace());
+            Ok(Kind::Space)
+        } else {
+            input.accept_while(|c| !c.is_whitespace());
+            Ok(Kind::Word)
+        }
+    }
+
+    #[test]
+    fn iter_including_trivia_round_trips_and_default_next_filters_it_out() {
+        let source = "foo  bar\tbaz";
+        let is_trivia = |kind: &Kind| *kind == Kind::Space;
+
+        let mut lossless = Lexer::with_trivia(source, &matcher, &is_trivia);
+        let reconstructed: String = lossless
+            .iter_including_trivia()
+            .map(|token| token.unwrap().into_text())
+            .collect();
+        assert_eq!(reconstructed, source);
+
+        let filtered = Lexer::with_trivia(source, &matcher, &is_trivia);
+        let words: Vec<&str> = filtered.map(|token| token.unwrap().into_text()).collect();
+        assert_eq!(words, vec!["foo", "bar", "baz"]);
+    }
+
+    #[test]
+    fn tokens_borrow_disjoint_correctly_spanned_slices_of_the_input() {
+        let source = "foo bar baz";
+        let tokens: Vec<Token<Kind>> = Lexer::new(source, &matcher, true)
+            .map(|token| token.unwrap())
+            .collect();
+
+        let texts: Vec<&str> = tokens.iter().map(Token::text).collect();
+        assert_eq!(texts, vec!["foo", "bar", "baz"]);
+
+        // every token's text is a slice of the original input, not a copy
+        for token in &tokens {
+            assert!(source.as_bytes().as_ptr_range().contains(&token.text().as_ptr()));
+        }
+
+        let spans: Vec<Span> = tokens.iter().map(Token::span).collect();
+        assert_eq!(spans[0], Span { start: 0, end: 3, line: 1, col: 1 });
+        assert_eq!(spans[1], Span { start: 4, end: 7, line: 1, col: 5 });
+        assert_eq!(spans[2], Span { start: 8, end: 11, line: 1, col: 9 });
+
+        // each span indexes back into the same source string as the token's own text
+        for (token, span) in tokens.iter().zip(&spans) {
+            assert_eq!(&source[span.start..span.end], token.text());
+        }
+    }
+}
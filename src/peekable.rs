@@ -0,0 +1,89 @@
+use crate::{Lexer, MatchResult, Token};
+
+/// A `Lexer` wrapper that buffers tokens so callers can look ahead and behind without
+/// losing them
+///
+/// Tokens are pulled from the underlying `Lexer` on demand and kept in an append-only
+/// `history`, with `pos` tracking how many have been consumed so far. `peek`/`peek_nth`
+/// read ahead of `pos` without advancing it; `lookback` reads the token just behind it.
+/// Because nothing already produced is ever discarded, rewinding to an earlier point in
+/// the token stream is just a matter of moving `pos` back.
+pub struct PeekableLexer<'a, K> {
+    lexer: Lexer<'a, K>,
+    history: Vec<MatchResult<Token<'a, K>>>,
+    pos: usize,
+}
+
+impl<'a, K> PeekableLexer<'a, K> {
+    /// Wrap a `Lexer` so its tokens can be peeked and rewound
+    pub fn new(lexer: Lexer<'a, K>) -> PeekableLexer<'a, K> {
+        PeekableLexer {
+            lexer,
+            history: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Pull tokens from the underlying lexer until `history` holds one at `index`, or the
+    /// lexer is exhausted
+    fn fill_to(&mut self, index: usize) {
+        while self.history.len() <= index {
+            match self.lexer.next() {
+                Some(token) => self.history.push(token),
+                None => break,
+            }
+        }
+    }
+
+    /// Peek at the next token without consuming it
+    pub fn peek(&mut self) -> Option<&MatchResult<Token<'a, K>>> {
+        self.peek_nth(0)
+    }
+
+    /// Peek `n` tokens ahead (`0` is the next token) without consuming anything
+    pub fn peek_nth(&mut self, n: usize) -> Option<&MatchResult<Token<'a, K>>> {
+        self.fill_to(self.pos + n);
+        self.history.get(self.pos + n)
+    }
+
+    /// Consume and return the next token
+    pub fn advance(&mut self) -> Option<&MatchResult<Token<'a, K>>> {
+        self.fill_to(self.pos);
+        if self.pos < self.history.len() {
+            self.pos += 1;
+            self.history.get(self.pos - 1)
+        } else {
+            None
+        }
+    }
+
+    /// The most recently consumed token, if any
+    pub fn lookback(&self) -> Option<&MatchResult<Token<'a, K>>> {
+        self.pos.checked_sub(1).and_then(|i| self.history.get(i))
+    }
+
+    /// A position in the buffered token history that `seek` can later rewind to
+    pub fn mark(&self) -> usize {
+        self.pos
+    }
+
+    /// Rewind consumption to a position previously returned by `mark`
+    ///
+    /// Only rewinds within tokens already produced; it cannot seek past the number of
+    /// tokens returned by `advance`/`peek` so far. Because `history` is append-only and
+    /// nothing is ever re-lexed, this is the only rewind mechanism `PeekableLexer` needs:
+    /// unlike `Lexer::checkpoint`/`restore`, there's no separate underlying cursor that
+    /// could drift out of sync with it.
+    pub fn seek(&mut self, mark: usize) {
+        assert!(mark <= self.history.len(), "seek: mark is ahead of buffered history");
+        self.pos = mark;
+    }
+}
+
+impl<'a, K: Clone> Iterator for PeekableLexer<'a, K> {
+    type Item = MatchResult<Token<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance().cloned()
+    }
+}
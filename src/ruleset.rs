@@ -0,0 +1,157 @@
+use std::cell::OnceCell;
+
+use regex::{Regex, RegexSet};
+
+use crate::{BufferedInput, MatchError, MatchResult, Matcher};
+
+struct Rule<K> {
+    kind: K,
+    skip: bool,
+}
+
+/// A declarative, regex-driven alternative to hand-written `Matcher` closures
+///
+/// Register `(pattern, kind, skip)` rules with [`RuleSet::rule`], then pass the finished
+/// `RuleSet` straight to `Lexer::new` as the matcher. On each call it tests the current
+/// position against every registered pattern, picks the longest match (ties broken by
+/// registration order), advances past it and returns the associated kind. Rules registered
+/// with `skip: true` (e.g. whitespace) are consumed and matched again from scratch rather
+/// than being returned as a token. Skipped input at the very end of the source, with no
+/// token after it, makes `try_match` return a `MatchError::Custom` error rather than ending
+/// the token stream.
+///
+/// ```no_run
+/// use generic_lexer::RuleSet;
+/// # #[derive(Clone, Debug)] enum TokenKind { Int, }
+/// let rules = RuleSet::new()
+///     .rule(r"[0-9]+", TokenKind::Int, false)
+///     .rule(r"\s+", TokenKind::Int, true);
+/// ```
+pub struct RuleSet<K> {
+    patterns: Vec<String>,
+    regexes: Vec<Regex>,
+    rules: Vec<Rule<K>>,
+    set: OnceCell<RegexSet>,
+}
+
+impl<K> Default for RuleSet<K> {
+    fn default() -> RuleSet<K> {
+        RuleSet::new()
+    }
+}
+
+impl<K> RuleSet<K> {
+    /// Create an empty rule set
+    pub fn new() -> RuleSet<K> {
+        RuleSet {
+            patterns: Vec::new(),
+            regexes: Vec::new(),
+            rules: Vec::new(),
+            set: OnceCell::new(),
+        }
+    }
+
+    /// Register a rule: when `pattern` produces the longest match at the current position,
+    /// a token of kind `kind` is emitted, or the match is skipped if `skip` is `true` (a
+    /// skipped match at the end of the input is an error; see `RuleSet`)
+    pub fn rule(mut self, pattern: &str, kind: K, skip: bool) -> RuleSet<K> {
+        let anchored = format!("^(?:{})", pattern);
+        let regex = Regex::new(&anchored).expect("RuleSet: invalid pattern");
+
+        self.patterns.push(anchored);
+        self.regexes.push(regex);
+        self.rules.push(Rule { kind, skip });
+
+        self
+    }
+
+    /// The combined `RegexSet`, compiled once from all registered patterns on first use
+    fn set(&self) -> &RegexSet {
+        self.set
+            .get_or_init(|| RegexSet::new(&self.patterns).expect("RuleSet: invalid patterns"))
+    }
+
+    /// Find the rule with the longest match at the start of `candidate`, breaking ties by
+    /// registration order
+    fn longest_match(&self, candidate: &str) -> Option<(usize, usize)> {
+        let mut best: Option<(usize, usize)> = None;
+
+        for idx in self.set().matches(candidate).into_iter() {
+            let len = self.regexes[idx].find(candidate).unwrap().end();
+            if best.is_none_or(|(_, best_len)| len > best_len) {
+                best = Some((idx, len));
+            }
+        }
+
+        best
+    }
+}
+
+impl<K: Clone> Matcher<K> for RuleSet<K> {
+    fn try_match(&self, first_char: char, input: &mut BufferedInput) -> MatchResult<K> {
+        let mut first_char = first_char;
+
+        loop {
+            let (idx, len) = match self.longest_match(input.remainder()) {
+                Some(found) => found,
+                None => return Err(MatchError::Unexpected(first_char)),
+            };
+
+            let already_consumed = first_char.len_utf8();
+            if len > already_consumed {
+                input.advance_by(len - already_consumed);
+            }
+
+            let rule = &self.rules[idx];
+            if !rule.skip {
+                return Ok(rule.kind.clone());
+            }
+
+            // trivia: drop what we matched and start the next token from scratch
+            input.take_buffer();
+            input.mark_token_start();
+            first_char = match input.accept() {
+                Some(c) => c,
+                None => return Err(MatchError::Custom("unexpected end of input after trivia".into())),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Kind {
+        Keyword,
+        Ident,
+        Space,
+    }
+
+    #[test]
+    fn equal_length_matches_are_broken_by_registration_order() {
+        let rules = RuleSet::new()
+            .rule("ident", Kind::Keyword, false)
+            .rule("[a-z]+", Kind::Ident, false);
+
+        let mut lexer = Lexer::new("ident", &rules, false);
+        let token = lexer.next().unwrap().unwrap();
+        assert_eq!(*token.kind(), Kind::Keyword);
+        assert_eq!(token.text(), "ident");
+    }
+
+    #[test]
+    fn skip_rules_are_consumed_without_emitting_a_token() {
+        let rules = RuleSet::new()
+            .rule("[a-z]+", Kind::Ident, false)
+            .rule(r"\s+", Kind::Space, true);
+
+        let tokens: Vec<&str> = Lexer::new("foo   bar", &rules, false)
+            .map(|token| token.unwrap().into_text())
+            .collect();
+
+        assert_eq!(tokens, vec!["foo", "bar"]);
+    }
+}